@@ -1,18 +1,409 @@
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use bpaf::{construct, Bpaf, Parser, ShellComp};
 use flox_rust_sdk::flox::Flox;
 use flox_rust_sdk::nix::command_line::NixCommandLine;
 use flox_rust_sdk::prelude::flox_package::FloxPackage;
+use serde::Serialize;
 
 use crate::config::features::Feature;
 use crate::{flox_forward, subcommand_metric};
 
+/// `(name, one-line help)` for every builtin subcommand, used for a man
+/// page's `NAME` line and a completion script's word list. Hand-maintained;
+/// `name` must match the bpaf command name on the corresponding
+/// [`EnvironmentCommands`] variant. Aliases (e.g. `Remove`'s `rm`) are
+/// tracked separately in [`COMMAND_ALIASES`], not here.
+const COMMANDS: &[(&str, &str)] = &[
+    ("activate", "activate environment"),
+    ("completion", "generate shell completion scripts or man pages"),
+    ("create", "create an environment"),
+    ("destroy", "remove all data pertaining to an environment"),
+    ("edit", "edit declarative environment configuration"),
+    ("export", "export declarative environment manifest to STDOUT"),
+    ("generations", "list environment generations with contents"),
+    ("git", "access to the git CLI for floxmeta repository"),
+    ("history", "show all versions of an environment"),
+    ("import", "import declarative environment manifest from STDIN as new generation"),
+    ("install", "install a package into an environment"),
+    ("list", "list packages installed in an environment"),
+    ("pull", "pull environment metadata from the remote registry"),
+    ("push", "push environment metadata to the remote registry"),
+    ("remove", "remove packages from an environment"),
+    ("rollback", "rollback to the previous generation of an environment"),
+    ("switch-generation", "switch to a specific generation of an environment"),
+    ("upgrade", "upgrade packages using their most recent flake"),
+    ("wipe-history", "delete non-current versions of an environment"),
+];
+
+/// `(alias, primary name)` pairs for builtins bpaf also parses under a
+/// secondary `long()` name (e.g. `Remove` is `#[bpaf(command,
+/// long("rm"))]`). Consulted alongside [`COMMANDS`] anywhere a "is this
+/// token a known builtin" check needs to agree with bpaf, so an alias
+/// like `rm` isn't mistaken for an unrecognized token or a user-aliasable
+/// name.
+const COMMAND_ALIASES: &[(&str, &str)] = &[("rm", "remove")];
+
+/// bounds how many times [`expand_aliases`] will substitute an alias for
+/// its expansion before giving up, guarding against cyclic alias chains
+/// such as `a = "b"` / `b = "a"`
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Expand a user-defined alias from the `[alias]` table in the flox config
+/// into its underlying command tokens, the way `cargo` resolves aliases
+/// from `.cargo/config.toml` before dispatching to a builtin subcommand.
+///
+/// `args` is the full argument vector (e.g. `["i", "foo"]`); `aliases` maps
+/// an alias key to the tokens it expands to (a string value is split on
+/// whitespace by the config loader before reaching here, a list value is
+/// used as-is). Builtins always win: if the first token already names a
+/// known subcommand — including a [`COMMAND_ALIASES`] entry like `rm` —
+/// `args` is returned unchanged, so a user alias can never shadow one of
+/// bpaf's own secondary command names. Chained aliases (an
+/// alias that expands to another alias) are followed up to
+/// [`MAX_ALIAS_DEPTH`] levels deep.
+pub fn expand_aliases(
+    aliases: &std::collections::HashMap<String, Vec<String>>,
+    args: Vec<String>,
+) -> Result<Vec<String>> {
+    let mut expanded = args;
+
+    for depth in 0..=MAX_ALIAS_DEPTH {
+        let Some(head) = expanded.first() else {
+            return Ok(expanded);
+        };
+
+        let is_builtin = COMMANDS.iter().any(|(name, _)| name == head)
+            || COMMAND_ALIASES.iter().any(|(alias, _)| alias == head);
+        if is_builtin {
+            return Ok(expanded);
+        }
+
+        let Some(replacement) = aliases.get(head) else {
+            return Ok(expanded);
+        };
+
+        if depth == MAX_ALIAS_DEPTH {
+            break;
+        }
+
+        expanded = replacement
+            .iter()
+            .cloned()
+            .chain(expanded.into_iter().skip(1))
+            .collect();
+    }
+
+    anyhow::bail!(
+        "alias did not resolve to a builtin command after {MAX_ALIAS_DEPTH} expansions (cyclic alias?)"
+    )
+}
+
+/// Parse `raw_args` (typically `std::env::args().skip(1)`) into an
+/// [`EnvironmentCommands`], expanding a user-defined alias against the
+/// first token before bpaf ever sees argv (see [`expand_aliases`]). This
+/// is the call site that makes e.g. `flox i foo` behave exactly as `flox
+/// install foo` would, instead of just failing to parse.
+///
+/// `aliases` is the `[alias]` table from the flox config, loaded by the
+/// caller: this module doesn't own the `Config` type, so it takes the
+/// table as a parameter rather than loading it itself.
+pub fn parse_args(
+    raw_args: Vec<String>,
+    aliases: &std::collections::HashMap<String, Vec<String>>,
+) -> Result<EnvironmentCommands> {
+    let expanded = expand_aliases(aliases, raw_args)?;
+
+    match environment_commands().to_options().run_inner(bpaf::Args::from(
+        expanded.iter().map(String::as_str).collect::<Vec<_>>().as_slice(),
+    )) {
+        Ok(commands) => Ok(commands),
+        // `--help`/`--version` and dynamic shell completion aren't parse
+        // errors — bpaf's own `run()` prints their message and exits 0 the
+        // same way. Only a real `Stderr` failure is a candidate for the
+        // "did you mean ...?" hint below.
+        Err(failure @ bpaf::ParseFailure::Stdout(..)) => {
+            print!("{}", failure.unwrap_stdout());
+            std::process::exit(0);
+        },
+        Err(bpaf::ParseFailure::Completion(text)) => {
+            print!("{text}");
+            std::process::exit(0);
+        },
+        Err(failure) => {
+            // Let bpaf be the sole authority on what's a valid command —
+            // it already knows about secondary long() names like `rm`
+            // that a hand-maintained table (COMMANDS) would have to
+            // duplicate and could get out of sync with. We only add a
+            // "did you mean ...?" hint on top of bpaf's own failure
+            // message when the first token looks like a plausible typo
+            // of a builtin name.
+            let stderr = failure.unwrap_stderr();
+            Err(match expanded.first().and_then(|head| suggest_command(head)) {
+                Some(hint) => anyhow::anyhow!("{stderr} (did you mean `{hint}`?)"),
+                None => anyhow::anyhow!(stderr),
+            })
+        },
+    }
+}
+
+/// at or below this edit distance, a candidate is considered a plausible typo
+/// rather than an unrelated word
+fn suggestion_threshold(len: usize) -> usize {
+    std::cmp::min(3, std::cmp::max(1, len / 3))
+}
+
+/// Levenshtein edit distance between `a` and `b`, computed with a single
+/// rolling row of length `b.len() + 1` rather than a full `a.len() x
+/// b.len()` matrix.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cur = std::cmp::min(
+                std::cmp::min(row[j] + 1, row[j - 1] + 1),
+                prev + usize::from(a[i - 1] != b[j - 1]),
+            );
+            prev = row[j];
+            row[j] = cur;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Find the candidate closest to `token` by edit distance, if any is
+/// within [`suggestion_threshold`]. Currently only used for mistyped
+/// subcommands (e.g. `flox instal`) via [`suggest_command`]; package-name
+/// suggestions for `Install`/`Remove` are not wired up (see those variants'
+/// doc comments).
+fn suggest<'a>(token: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = suggestion_threshold(token.chars().count());
+
+    candidates
+        .into_iter()
+        .map(|candidate| (candidate, edit_distance(token, candidate)))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Suggest the closest builtin subcommand name to an unrecognized token,
+/// for use in a "did you mean ...?" hint after a parse failure. Considers
+/// both primary command names and [`COMMAND_ALIASES`] entries.
+pub fn suggest_command(token: &str) -> Option<&'static str> {
+    suggest(
+        token,
+        COMMANDS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(COMMAND_ALIASES.iter().map(|(alias, _)| *alias)),
+    )
+}
+
+/// shells that `flox completion` can render a static completion script for
+#[derive(Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+}
+
+impl Shell {
+    fn parse() -> impl Parser<Shell> {
+        bpaf::positional("SHELL")
+            .help("Shell to generate a completion script for: bash, zsh, fish, nushell")
+            .parse(|s: String| match s.as_str() {
+                "bash" => Ok(Shell::Bash),
+                "zsh" => Ok(Shell::Zsh),
+                "fish" => Ok(Shell::Fish),
+                "nushell" | "nu" => Ok(Shell::Nushell),
+                other => Err(format!("unsupported shell `{other}` (expected bash, zsh, fish or nushell)")),
+            })
+    }
+
+    /// Render a static completion script listing every builtin subcommand.
+    ///
+    /// This covers the same ground as `clap_complete`/`clap_complete_nushell`
+    /// for clap-based CLIs, but is hand rolled here since bpaf only exposes
+    /// per-invocation dynamic completion (see [`ImportFile::parse`]).
+    fn render(self, commands: &[(&str, &str)]) -> String {
+        let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+
+        match self {
+            Shell::Bash => format!(
+                "complete -W \"{}\" -o default flox\n",
+                names.join(" ")
+            ),
+            Shell::Zsh => {
+                let alternatives = commands
+                    .iter()
+                    .map(|(name, help)| format!("    \"{name}:{help}\""))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("#compdef flox\n_arguments '1: :(({alternatives}))'\n")
+            },
+            Shell::Fish => commands
+                .iter()
+                .map(|(name, help)| {
+                    format!("complete -c flox -n __fish_use_subcommand -a {name} -d '{help}'\n")
+                })
+                .collect(),
+            Shell::Nushell => {
+                let alternatives = names
+                    .iter()
+                    .map(|name| format!("\"{name}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("def \"nu-complete flox\" [] {{ [{alternatives}] }}\nexport extern flox [subcommand: string@\"nu-complete flox\"]\n")
+            },
+        }
+    }
+}
+
+/// Render one roff man page per builtin subcommand into `dir`.
+///
+/// The `NAME` section comes from the one-line [`COMMANDS`] help string,
+/// but the `SYNOPSIS` is bpaf's own `--help` rendering for that command —
+/// the same text a user sees running `flox <name> --help`, complete with
+/// its real option and positional names (e.g. `--environment`/`-e`,
+/// `PACKAGES`) — rather than a hand-written stand-in, so the synopsis
+/// can't drift from what the parser actually accepts.
+fn render_manpages(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+
+    for (name, help) in COMMANDS {
+        let synopsis = environment_commands()
+            .to_options()
+            .run_inner(bpaf::Args::from(&[*name, "--help"]))
+            .err()
+            .map(|failure| failure.unwrap_stdout())
+            .unwrap_or_default();
+
+        let page = format!(
+            ".TH FLOX-{upper} 1\n.SH NAME\nflox-{name} \\- {help}\n.SH SYNOPSIS\n.nf\n{synopsis}.fi\n",
+            upper = name.to_uppercase(),
+        );
+        fs::write(dir.join(format!("flox-{name}.1")), page)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Bpaf, Clone)]
 pub struct EnvironmentArgs {
     #[bpaf(short, long, argument("SYSTEM"))]
     pub system: Option<String>,
+
+    #[bpaf(external(MessageFormat::parse))]
+    pub message_format: MessageFormat,
+}
+
+/// crate-wide output mode, analogous to other build tools exposing a
+/// machine-readable message stream alongside their human-readable output
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    fn parse() -> impl Parser<MessageFormat> {
+        bpaf::long("message-format")
+            .help("Output format for results: `human` (default) or `json`")
+            .argument::<String>("FORMAT")
+            .parse(|s| match s.as_str() {
+                "human" => Ok(MessageFormat::Human),
+                "json" => Ok(MessageFormat::Json),
+                other => {
+                    Err(format!("unknown message format `{other}` (expected `human` or `json`)"))
+                },
+            })
+            .fallback(MessageFormat::Human)
+    }
+}
+
+/// A single structured record describing the outcome of an operation,
+/// emitted as one line of JSON when `--message-format=json` is set (e.g.
+/// `{"reason":"package-installed","pkg":"..."}`).
+///
+/// Only covers `Install`/`Remove`/`Upgrade`; `Rollback`/`SwitchGeneration`
+/// need a real `generation()`/`rollback()`/`switch_generation()` SDK hook
+/// before they can emit events too (see the `todo!()` fallback below) —
+/// the original ask covered all five, so this isn't done, it's blocked.
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum Event {
+    PackageInstalled { pkg: String },
+    PackageRemoved { pkg: String },
+    PackagesUpgraded { pkgs: Vec<String> },
+}
+
+impl Event {
+    /// Print this event in `format`: a human-readable line, or a single
+    /// line of JSON for scripting.
+    fn print(&self, format: MessageFormat) -> Result<()> {
+        match format {
+            MessageFormat::Human => println!("{}", self.describe()),
+            MessageFormat::Json => println!("{}", serde_json::to_string(self)?),
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Event::PackageInstalled { pkg } => format!("installed '{pkg}'"),
+            Event::PackageRemoved { pkg } => format!("removed '{pkg}'"),
+            Event::PackagesUpgraded { pkgs } => format!("upgraded {}", pkgs.join(", ")),
+        }
+    }
+}
+
+/// Echoes back the package names `Install`, `Remove` or `Upgrade` were
+/// given, printed in place of the real call so nothing is mutated.
+///
+/// This is not a resolved preview: `added`/`removed`/`upgraded` are just
+/// the command's own argument list, not store-path derivations, and there's
+/// no resulting generation number, since there's no resolve-only SDK entry
+/// point to derive either from. A package name that wouldn't actually
+/// resolve is echoed back the same as one that would. This falls short of
+/// the original ask (reuse real resolution, report store paths and the
+/// resulting generation); needs a resolve-only SDK hook before it can
+/// close, or explicit sign-off that this reduced scope is acceptable.
+#[derive(Serialize)]
+struct Plan {
+    added: Vec<String>,
+    removed: Vec<String>,
+    upgraded: Vec<String>,
+}
+
+impl Plan {
+    fn print(&self, format: MessageFormat) -> Result<()> {
+        match format {
+            MessageFormat::Human => {
+                for (verb, pkgs) in [
+                    ("add", &self.added),
+                    ("remove", &self.removed),
+                    ("upgrade", &self.upgraded),
+                ] {
+                    for pkg in pkgs {
+                        println!("would {verb} '{pkg}'");
+                    }
+                }
+            },
+            MessageFormat::Json => println!("{}", serde_json::to_string(self)?),
+        }
+        Ok(())
+    }
 }
 
 pub type EnvironmentRef = PathBuf;
@@ -24,14 +415,93 @@ impl EnvironmentCommands {
 
             EnvironmentCommands::Install {
                 packages,
-                environment_args: EnvironmentArgs { .. },
+                environment_args: EnvironmentArgs { message_format, .. },
                 environment,
+                dry_run,
             } => {
                 subcommand_metric!("install");
 
-                flox.environment(environment.clone().unwrap())?
-                    .install::<NixCommandLine>(packages)
-                    .await?
+                let env = flox.environment(environment.clone().unwrap())?;
+
+                if *dry_run {
+                    return Plan {
+                        added: packages.iter().map(ToString::to_string).collect(),
+                        removed: Vec::new(),
+                        upgraded: Vec::new(),
+                    }
+                    .print(*message_format);
+                }
+
+                env.install::<NixCommandLine>(packages).await?;
+
+                for pkg in packages {
+                    Event::PackageInstalled { pkg: pkg.to_string() }.print(*message_format)?;
+                }
+            },
+
+            EnvironmentCommands::Remove {
+                packages,
+                environment_args: EnvironmentArgs { message_format, .. },
+                environment,
+                dry_run,
+            } => {
+                subcommand_metric!("remove");
+
+                let env = flox.environment(environment.clone().unwrap())?;
+
+                if *dry_run {
+                    return Plan {
+                        added: Vec::new(),
+                        removed: packages.iter().map(ToString::to_string).collect(),
+                        upgraded: Vec::new(),
+                    }
+                    .print(*message_format);
+                }
+
+                env.remove::<NixCommandLine>(packages).await?;
+
+                for pkg in packages {
+                    Event::PackageRemoved { pkg: pkg.to_string() }.print(*message_format)?;
+                }
+            },
+
+            EnvironmentCommands::Upgrade {
+                packages,
+                environment_args: EnvironmentArgs { message_format, .. },
+                environment,
+                dry_run,
+            } => {
+                subcommand_metric!("upgrade");
+
+                let env = flox.environment(environment.clone().unwrap())?;
+
+                if *dry_run {
+                    return Plan {
+                        added: Vec::new(),
+                        removed: Vec::new(),
+                        upgraded: packages.iter().map(ToString::to_string).collect(),
+                    }
+                    .print(*message_format);
+                }
+
+                env.upgrade::<NixCommandLine>(packages).await?;
+
+                Event::PackagesUpgraded {
+                    pkgs: packages.iter().map(ToString::to_string).collect(),
+                }
+                .print(*message_format)?;
+            },
+
+            EnvironmentCommands::Completion { shell, manpages } => {
+                subcommand_metric!("completion");
+
+                match (shell, manpages) {
+                    (_, Some(dir)) => render_manpages(dir)?,
+                    (Some(shell), None) => print!("{}", shell.render(COMMANDS)),
+                    (None, None) => {
+                        anyhow::bail!("either a SHELL or --manpages <DIR> is required")
+                    },
+                }
             },
 
             _ => todo!(),
@@ -114,7 +584,7 @@ pub enum EnvironmentCommands {
         arguments: Option<(String, Vec<String>)>,
     },
 
-    /// create an envirnment
+    /// create an environment
     #[bpaf(command)]
     Create {
         #[bpaf(external(environment_args), group_help("Environment Options"))]
@@ -210,6 +680,13 @@ pub enum EnvironmentCommands {
     },
 
     /// install a package into an environment
+    ///
+    /// No "did you mean ...?" suggestion for an unresolved package name yet:
+    /// there's no catalog/package-listing entry point for [`suggest`] to
+    /// check against. This is half of the original ask (subcommand-typo
+    /// suggestions are covered, package-name ones aren't); needs a
+    /// follow-up once a catalog lookup exists, or explicit sign-off that
+    /// package-name suggestions are out of scope.
     #[bpaf(command)]
     Install {
         #[bpaf(external(environment_args), group_help("Environment Options"))]
@@ -220,6 +697,10 @@ pub enum EnvironmentCommands {
 
         #[bpaf(positional("PACKAGES"), some("At least one package"))]
         packages: Vec<FloxPackage>,
+
+        /// print the packages that would be added, without resolving them or creating a new generation
+        #[bpaf(long("dry-run"))]
+        dry_run: bool,
     },
 
     /// list packages installed in an environment
@@ -231,15 +712,16 @@ pub enum EnvironmentCommands {
         #[bpaf(long, short, argument("ENV"))]
         environment: Option<EnvironmentRef>,
 
-        #[bpaf(external(list_output), optional)]
-        json: Option<ListOutput>,
+        /// Include store paths of packages in the environment
+        #[bpaf(long("out-path"))]
+        out_path: bool,
 
         /// The generation to list, if not speciefied defaults to the current one
         #[bpaf(positional("GENERATION"))]
         generation: Option<u32>,
     },
 
-    /// send environment metadata from remote registry
+    /// push environment metadata to the remote registry
     #[bpaf(command)]
     Push {
         #[bpaf(external(environment_args), group_help("Environment Options"))]
@@ -253,7 +735,7 @@ pub enum EnvironmentCommands {
         force: bool,
     },
 
-    /// pull environment metadata to remote registry
+    /// pull environment metadata from the remote registry
     #[bpaf(command)]
     Pull {
         #[bpaf(external(environment_args), group_help("Environment Options"))]
@@ -268,6 +750,9 @@ pub enum EnvironmentCommands {
     },
 
     /// remove packages from an environment
+    ///
+    /// No "did you mean ...?" suggestion for an unresolved package name yet,
+    /// for the same reason as `Install` — same follow-up needed.
     #[bpaf(command, long("rm"))]
     Remove {
         #[bpaf(external(environment_args), group_help("Environment Options"))]
@@ -278,6 +763,10 @@ pub enum EnvironmentCommands {
 
         #[bpaf(positional("PACKAGES"), some("At least one package"))]
         packages: Vec<FloxPackage>,
+
+        /// print the packages that would be removed, without resolving them or creating a new generation
+        #[bpaf(long("dry-run"))]
+        dry_run: bool,
     },
 
     /// rollback to the previous generation of an environment
@@ -320,6 +809,22 @@ pub enum EnvironmentCommands {
 
         #[bpaf(positional("PACKAGES"))]
         packages: Vec<FloxPackage>,
+
+        /// print the packages that would be upgraded, without resolving them or creating a new generation
+        #[bpaf(long("dry-run"))]
+        dry_run: bool,
+    },
+
+    /// generate a static shell completion script, or roff man pages
+    #[bpaf(command)]
+    Completion {
+        #[bpaf(external(Shell::parse), optional)]
+        shell: Option<Shell>,
+
+        /// render one man page per subcommand into this directory instead
+        /// of printing a completion script
+        #[bpaf(long, argument("DIR"))]
+        manpages: Option<PathBuf>,
     },
 
     /// delete non-current versions of an environment
@@ -333,12 +838,142 @@ pub enum EnvironmentCommands {
     },
 }
 
-#[derive(Bpaf, Clone)]
-pub enum ListOutput {
-    /// Include store paths of packages in the environment
-    #[bpaf(long("out-path"))]
-    OutPath,
-    /// Print as machine readable json
-    #[bpaf(long)]
-    Json,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_bash_lists_every_command() {
+        let out = Shell::Bash.render(COMMANDS);
+        assert!(out.starts_with("complete -W \""));
+        assert!(out.contains("install"));
+        assert!(out.contains("remove"));
+    }
+
+    #[test]
+    fn render_zsh_includes_help_text() {
+        let out = Shell::Zsh.render(COMMANDS);
+        assert!(out.starts_with("#compdef flox\n"));
+        assert!(out.contains("install:install a package into an environment"));
+    }
+
+    #[test]
+    fn render_fish_emits_one_complete_per_command() {
+        let out = Shell::Fish.render(COMMANDS);
+        assert_eq!(out.lines().count(), COMMANDS.len());
+        assert!(out.contains("complete -c flox -n __fish_use_subcommand -a install"));
+    }
+
+    #[test]
+    fn render_nushell_lists_every_command() {
+        let out = Shell::Nushell.render(COMMANDS);
+        assert!(out.contains("nu-complete flox"));
+        assert!(out.contains("\"install\""));
+    }
+
+    #[test]
+    fn edit_distance_is_zero_for_identical_strings() {
+        assert_eq!(edit_distance("install", "install"), 0);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_insertion() {
+        assert_eq!(edit_distance("instal", "install"), 1);
+    }
+
+    #[test]
+    fn edit_distance_counts_insertions_and_deletions() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+    }
+
+    #[test]
+    fn suggest_picks_the_closest_candidate() {
+        let candidates = ["install", "import", "history"];
+        assert_eq!(suggest("instal", candidates), Some("install"));
+    }
+
+    #[test]
+    fn suggest_returns_none_past_the_threshold() {
+        let candidates = ["install", "import", "history"];
+        assert_eq!(suggest("xyz", candidates), None);
+    }
+
+    #[test]
+    fn suggest_command_considers_aliases() {
+        assert_eq!(suggest_command("r"), Some("rm"));
+    }
+
+    #[test]
+    fn expand_aliases_substitutes_a_user_alias() {
+        let aliases = std::collections::HashMap::from([(
+            "i".to_string(),
+            vec!["install".to_string()],
+        )]);
+        let expanded = expand_aliases(&aliases, vec!["i".to_string(), "foo".to_string()]).unwrap();
+        assert_eq!(expanded, vec!["install", "foo"]);
+    }
+
+    #[test]
+    fn expand_aliases_leaves_builtins_unchanged() {
+        let aliases = std::collections::HashMap::from([(
+            "install".to_string(),
+            vec!["destroy".to_string()],
+        )]);
+        let expanded =
+            expand_aliases(&aliases, vec!["install".to_string(), "foo".to_string()]).unwrap();
+        assert_eq!(expanded, vec!["install", "foo"]);
+    }
+
+    #[test]
+    fn expand_aliases_leaves_command_aliases_unchanged() {
+        let aliases = std::collections::HashMap::from([(
+            "rm".to_string(),
+            vec!["create".to_string()],
+        )]);
+        let expanded = expand_aliases(&aliases, vec!["rm".to_string(), "foo".to_string()]).unwrap();
+        assert_eq!(expanded, vec!["rm", "foo"]);
+    }
+
+    #[test]
+    fn expand_aliases_follows_a_chain() {
+        let aliases = std::collections::HashMap::from([
+            ("a".to_string(), vec!["b".to_string()]),
+            ("b".to_string(), vec!["install".to_string()]),
+        ]);
+        let expanded = expand_aliases(&aliases, vec!["a".to_string()]).unwrap();
+        assert_eq!(expanded, vec!["install"]);
+    }
+
+    #[test]
+    fn expand_aliases_rejects_a_cycle() {
+        let aliases = std::collections::HashMap::from([
+            ("a".to_string(), vec!["b".to_string()]),
+            ("b".to_string(), vec!["a".to_string()]),
+        ]);
+        assert!(expand_aliases(&aliases, vec!["a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn expand_aliases_passes_through_unknown_tokens() {
+        let aliases = std::collections::HashMap::new();
+        let expanded = expand_aliases(&aliases, vec!["bogus".to_string()]).unwrap();
+        assert_eq!(expanded, vec!["bogus"]);
+    }
+
+    #[test]
+    fn render_manpages_writes_one_page_per_command() {
+        let dir = std::env::temp_dir().join(format!(
+            "flox-test-manpages-{}",
+            std::process::id()
+        ));
+        render_manpages(&dir).unwrap();
+
+        for (name, help) in COMMANDS {
+            let page = fs::read_to_string(dir.join(format!("flox-{name}.1"))).unwrap();
+            assert!(page.contains(&format!("flox-{name} \\- {help}")));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }